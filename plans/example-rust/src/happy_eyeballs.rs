@@ -0,0 +1,87 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use tokio::net::{lookup_host, TcpStream};
+use tokio::time::sleep;
+
+use testground::client::Client;
+
+/// Delay between starting successive connection attempts, per RFC 8305.
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Family {
+    V4,
+    V6,
+}
+
+pub async fn connect(
+    client: &Client,
+    host: &str,
+    port: u16,
+) -> Result<(TcpStream, SocketAddr, Family), Box<dyn std::error::Error>> {
+    let candidates = sorted_candidates(host, port).await?;
+    if candidates.is_empty() {
+        return Err(format!("no addresses resolved for {host}").into());
+    }
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let attempts: Vec<_> = candidates
+        .into_iter()
+        .enumerate()
+        .map(|(i, addr)| {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                sleep(ATTEMPT_DELAY * i as u32).await;
+                if let Ok(stream) = TcpStream::connect(addr).await {
+                    let _ = tx.send((stream, addr));
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+
+    let (stream, addr) = rx
+        .recv()
+        .await
+        .ok_or_else(|| format!("no candidate for {host} was reachable"))?;
+
+    for attempt in attempts {
+        attempt.abort();
+    }
+
+    let family = match addr.ip() {
+        IpAddr::V4(_) => Family::V4,
+        IpAddr::V6(_) => Family::V6,
+    };
+    client
+        .record_metric(format!("happy_eyeballs_winner_{:?}", family), 1.0)
+        .await?;
+
+    Ok((stream, addr, family))
+}
+
+async fn sorted_candidates(
+    host: &str,
+    port: u16,
+) -> Result<Vec<SocketAddr>, Box<dyn std::error::Error>> {
+    let resolved: Vec<SocketAddr> = lookup_host((host, port)).await?.collect();
+
+    let mut v6: Vec<SocketAddr> = resolved.iter().copied().filter(|a| a.is_ipv6()).collect();
+    let mut v4: Vec<SocketAddr> = resolved.iter().copied().filter(|a| a.is_ipv4()).collect();
+
+    let mut interleaved = Vec::with_capacity(v6.len() + v4.len());
+    loop {
+        match (v6.is_empty(), v4.is_empty()) {
+            (true, true) => break,
+            (false, true) => interleaved.append(&mut v6),
+            (true, false) => interleaved.append(&mut v4),
+            (false, false) => {
+                interleaved.push(v6.remove(0));
+                interleaved.push(v4.remove(0));
+            }
+        }
+    }
+
+    Ok(interleaved)
+}
@@ -1,50 +1,102 @@
-use std::net::Ipv4Addr;
-use tokio::net::{TcpListener, TcpStream};
+mod happy_eyeballs;
+mod mesh;
+mod reachability;
+mod reconnect;
+mod swarm_info;
+mod udp_relay;
 
 const LISTENING_PORT: u16 = 1234;
+const ANCHOR_PORT: u16 = 1233;
+const RECONNECT_PORT: u16 = 1232;
+const UDP_PORT: u16 = 1243;
+const UDP_TEST_PAYLOAD: &[u8] = b"canary-udp-payload";
+const PROBE_TCP_PORTS: [u16; reachability::PORTS_PER_PROBE] = [1235, 1236, 1237, 1238];
+const PROBE_UDP_PORTS: [u16; reachability::PORTS_PER_PROBE] = [1239, 1240, 1241, 1242];
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let (client, _run_parameters) = testground::client::Client::new().await?;
+    let (client, run_parameters) = testground::client::Client::new().await?;
     client.wait_network_initialized().await?;
 
-    let local_addr = &if_addrs::get_if_addrs()?
+    let udp_test_enabled = run_parameters
+        .test_instance_params
+        .get("udp_test")
+        .map(|v| v == "true")
+        .unwrap_or(false);
+    let reconnect_drops = run_parameters
+        .test_instance_params
+        .get("reconnect_drops")
+        .and_then(|v| v.parse::<u32>().ok());
+
+    let local_addr = match if_addrs::get_if_addrs()?
         .into_iter()
         .find(|iface| iface.name == "eth1")
         .ok_or("Could not find interface eth1")?
         .addr
-        .ip();
+        .ip()
+    {
+        std::net::IpAddr::V4(addr) => addr,
+        addr => {
+            client.record_failure("Unexpected local IP address").await?;
+            panic!("Unexpected local IP address {:?}", addr);
+        }
+    };
 
-    match local_addr {
-        std::net::IpAddr::V4(addr) if addr.octets()[3] == 2 => {
-            println!("Test instance, listening for incoming connections.");
+    println!(
+        "Test instance, joining a {}-instance mesh.",
+        run_parameters.test_instance_count
+    );
 
-            let listener = TcpListener::bind((*addr, LISTENING_PORT)).await?;
+    let results = mesh::run_full_mesh(
+        &client,
+        &run_parameters,
+        local_addr,
+        LISTENING_PORT,
+        udp_test_enabled,
+        reconnect_drops,
+    )
+    .await?;
 
-            client.signal("listening".to_string()).await?;
+    for link in &results {
+        println!(
+            "{} -> {}: {}",
+            link.from,
+            link.to,
+            if link.success { "ok" } else { "failed" }
+        );
+    }
 
-            let _ = listener.accept().await?;
-            println!("Established inbound TCP connection.");
-        }
-        std::net::IpAddr::V4(addr) if addr.octets()[3] == 3 => {
-            println!("Test instance, connecting to listening instance.");
-
-            client.barrier("listening".to_string(), 1).await?;
-
-            let remote_addr: Ipv4Addr = {
-                let mut octets = addr.octets();
-                octets[3] = 2;
-                octets.into()
-            };
-            let _stream = TcpStream::connect((remote_addr, LISTENING_PORT)).await?;
-            println!("Established outbound TCP connection.");
-        }
-        addr => {
-            client.record_failure("Unexpected local IP address").await?;
-            panic!("Unexpected local IP address {:?}", addr);
+    if let Some(host) = run_parameters.test_instance_params.get("connect_host") {
+        let port: u16 = run_parameters
+            .test_instance_params
+            .get("connect_port")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(LISTENING_PORT);
+
+        match happy_eyeballs::connect(&client, host, port).await {
+            Ok((_stream, addr, family)) => {
+                println!("Connected to {host} via {addr} ({family:?}).");
+            }
+            Err(e) => {
+                client
+                    .record_failure(format!("dual-stack connect to {host} failed: {e}"))
+                    .await?;
+            }
         }
     }
 
+    if let Some(expected_peer_id) = run_parameters.test_instance_params.get("swarm_check_peer_id")
+    {
+        let expected_transport = run_parameters
+            .test_instance_params
+            .get("swarm_check_transport")
+            .map(String::as_str)
+            .unwrap_or("/tcp/");
+
+        swarm_info::assert_peered(&client, expected_peer_id, expected_transport).await?;
+        println!("Checked libp2p peering for {expected_peer_id}.");
+    }
+
     client.record_success().await?;
     println!("Done!");
     Ok(())
@@ -0,0 +1,191 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use tokio::net::{TcpListener, TcpStream};
+
+use testground::client::Client;
+use testground::RunParameters;
+
+use crate::reachability::{self, ProbeRequest};
+use crate::reconnect;
+use crate::udp_relay;
+use crate::{
+    ANCHOR_PORT, PROBE_TCP_PORTS, PROBE_UDP_PORTS, RECONNECT_PORT, UDP_PORT, UDP_TEST_PAYLOAD,
+};
+
+#[derive(Debug)]
+pub struct LinkResult {
+    pub from: u64,
+    pub to: u64,
+    pub success: bool,
+}
+
+/// Rewrites the last octet of `base`, continuing the `.2`, `.3`, ...
+/// convention the original two-instance test used.
+pub fn derive_addr(base: Ipv4Addr, seq: u64) -> Ipv4Addr {
+    let mut octets = base.octets();
+    octets[3] = (seq + 1) as u8;
+    octets.into()
+}
+
+/// Only the plain connectivity matrix scales to the full instance count;
+/// the reachability/UDP/reconnect checks below still only run between
+/// instances 1 and 2.
+pub async fn run_full_mesh(
+    client: &Client,
+    run_parameters: &RunParameters,
+    local_addr: Ipv4Addr,
+    port: u16,
+    udp_test_enabled: bool,
+    reconnect_drops: Option<u32>,
+) -> Result<Vec<LinkResult>, Box<dyn std::error::Error>> {
+    let total_instances = run_parameters.test_instance_count;
+
+    let listener = TcpListener::bind((local_addr, port)).await?;
+    let seq = client.signal("listening".to_string()).await?;
+    let accept_task = tokio::spawn(accept_all(listener, total_instances - 1));
+    let anchor_server_task = (seq == 2).then(|| {
+        tokio::spawn(run_anchor_server(
+            local_addr,
+            udp_test_enabled,
+            reconnect_drops,
+        ))
+    });
+
+    client
+        .barrier("listening".to_string(), total_instances)
+        .await?;
+
+    let mut results = Vec::with_capacity((total_instances - 1) as usize);
+    for peer_seq in 1..=total_instances {
+        if peer_seq == seq {
+            continue;
+        }
+
+        let peer_addr = SocketAddr::from((derive_addr(local_addr, peer_seq), port));
+        let success = TcpStream::connect(peer_addr).await.is_ok();
+
+        if !success {
+            client
+                .record_failure(format!("{seq} could not reach {peer_seq} at {peer_addr}"))
+                .await?;
+        }
+
+        results.push(LinkResult {
+            from: seq,
+            to: peer_seq,
+            success,
+        });
+    }
+
+    accept_task.await??;
+
+    if seq == 1 && total_instances >= 2 {
+        run_anchor_client(client, local_addr, udp_test_enabled, reconnect_drops).await?;
+    }
+    if let Some(task) = anchor_server_task {
+        task.await??;
+    }
+
+    Ok(results)
+}
+
+async fn accept_all(listener: TcpListener, count: u64) -> Result<(), Box<dyn std::error::Error>> {
+    for _ in 0..count {
+        let _ = listener.accept().await?;
+    }
+    Ok(())
+}
+
+async fn run_anchor_server(
+    local_addr: Ipv4Addr,
+    udp_test_enabled: bool,
+    reconnect_drops: Option<u32>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind((local_addr, ANCHOR_PORT)).await?;
+    let (mut stream, peer_addr) = listener.accept().await?;
+    reachability::run_echo_server(&mut stream, peer_addr, local_addr.into()).await?;
+
+    if udp_test_enabled {
+        udp_relay::run_echo_server((local_addr, UDP_PORT).into()).await?;
+    }
+
+    if let Some(drops) = reconnect_drops {
+        let listener = TcpListener::bind((local_addr, RECONNECT_PORT)).await?;
+        reconnect::run_drop_server(&listener, drops).await?;
+    }
+
+    Ok(())
+}
+
+async fn run_anchor_client(
+    client: &Client,
+    local_addr: Ipv4Addr,
+    udp_test_enabled: bool,
+    reconnect_drops: Option<u32>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let peer_addr = derive_addr(local_addr, 2);
+
+    let request = ProbeRequest {
+        tcp_ports: PROBE_TCP_PORTS,
+        udp_ports: PROBE_UDP_PORTS,
+    };
+    reachability::probe_reachability(
+        client,
+        local_addr.into(),
+        (peer_addr, ANCHOR_PORT).into(),
+        request,
+    )
+    .await?;
+
+    if udp_test_enabled {
+        udp_relay::connect_and_verify_echo(
+            (local_addr, 0).into(),
+            (peer_addr, UDP_PORT).into(),
+            UDP_TEST_PAYLOAD,
+        )
+        .await?;
+    }
+
+    if let Some(drops) = reconnect_drops {
+        reconnect::connect_with_resilience(client, (peer_addr, RECONNECT_PORT).into(), drops)
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derive_addr_continues_the_dot2_dot3_convention() {
+        let base: Ipv4Addr = "10.0.0.1".parse().unwrap();
+        assert_eq!(derive_addr(base, 1), "10.0.0.2".parse::<Ipv4Addr>().unwrap());
+        assert_eq!(derive_addr(base, 2), "10.0.0.3".parse::<Ipv4Addr>().unwrap());
+    }
+
+    #[test]
+    fn mesh_dial_targets_are_every_other_instance_exactly_once() {
+        let base: Ipv4Addr = "10.0.0.1".parse().unwrap();
+        let total_instances = 4u64;
+
+        for seq in 1..=total_instances {
+            let own_addr = derive_addr(base, seq);
+            let dial_targets: Vec<Ipv4Addr> = (1..=total_instances)
+                .filter(|&peer_seq| peer_seq != seq)
+                .map(|peer_seq| derive_addr(base, peer_seq))
+                .collect();
+            assert!(
+                !dial_targets.contains(&own_addr),
+                "instance {seq} would dial itself"
+            );
+
+            let expected: Vec<Ipv4Addr> = (1..=total_instances)
+                .map(|s| derive_addr(base, s))
+                .filter(|&addr| addr != own_addr)
+                .collect();
+            assert_eq!(dial_targets, expected);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::time::timeout;
+
+use testground::client::Client;
+
+use crate::udp_relay::{read_tcp_frame, write_tcp_frame};
+
+pub const PORTS_PER_PROBE: usize = 4;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+const REQUEST_LEN: usize = PORTS_PER_PROBE * 2 * 2;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ProbeRequest {
+    pub tcp_ports: [u16; PORTS_PER_PROBE],
+    pub udp_ports: [u16; PORTS_PER_PROBE],
+}
+
+impl ProbeRequest {
+    fn encode(&self) -> [u8; REQUEST_LEN] {
+        let mut buf = [0u8; REQUEST_LEN];
+        for (i, port) in self.tcp_ports.iter().enumerate() {
+            buf[i * 2..i * 2 + 2].copy_from_slice(&port.to_be_bytes());
+        }
+        let udp_off = PORTS_PER_PROBE * 2;
+        for (i, port) in self.udp_ports.iter().enumerate() {
+            buf[udp_off + i * 2..udp_off + i * 2 + 2].copy_from_slice(&port.to_be_bytes());
+        }
+        buf
+    }
+
+    fn decode(buf: &[u8; REQUEST_LEN]) -> Self {
+        let mut tcp_ports = [0u16; PORTS_PER_PROBE];
+        let mut udp_ports = [0u16; PORTS_PER_PROBE];
+        let udp_off = PORTS_PER_PROBE * 2;
+        for i in 0..PORTS_PER_PROBE {
+            tcp_ports[i] = u16::from_be_bytes([buf[i * 2], buf[i * 2 + 1]]);
+            udp_ports[i] =
+                u16::from_be_bytes([buf[udp_off + i * 2], buf[udp_off + i * 2 + 1]]);
+        }
+        Self {
+            tcp_ports,
+            udp_ports,
+        }
+    }
+}
+
+pub async fn run_echo_server(
+    stream: &mut TcpStream,
+    peer_addr: SocketAddr,
+    local_addr: IpAddr,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut request_buf = [0u8; REQUEST_LEN];
+    stream.read_exact(&mut request_buf).await?;
+    let request = ProbeRequest::decode(&request_buf);
+
+    for port in request.tcp_ports {
+        let _ = TcpStream::connect(SocketAddr::new(peer_addr.ip(), port)).await;
+    }
+
+    if let Ok(udp_socket) = UdpSocket::bind((local_addr, 0)).await {
+        for port in request.udp_ports {
+            let _ = udp_socket
+                .send_to(b"probe", SocketAddr::new(peer_addr.ip(), port))
+                .await;
+        }
+    }
+
+    let reply = peer_addr.to_string();
+    write_tcp_frame(stream, reply.as_bytes()).await?;
+
+    Ok(())
+}
+
+pub async fn probe_reachability(
+    client: &Client,
+    local_addr: IpAddr,
+    server_addr: SocketAddr,
+    request: ProbeRequest,
+) -> Result<SocketAddr, Box<dyn std::error::Error>> {
+    let tcp_listeners = {
+        let mut listeners = Vec::with_capacity(PORTS_PER_PROBE);
+        for port in request.tcp_ports {
+            listeners.push(TcpListener::bind((local_addr, port)).await?);
+        }
+        listeners
+    };
+    let udp_sockets = {
+        let mut sockets = Vec::with_capacity(PORTS_PER_PROBE);
+        for port in request.udp_ports {
+            sockets.push(UdpSocket::bind((local_addr, port)).await?);
+        }
+        sockets
+    };
+
+    let mut stream = TcpStream::connect(server_addr).await?;
+    stream.write_all(&request.encode()).await?;
+
+    let reply = read_tcp_frame(&mut stream).await?;
+    let observed_addr: SocketAddr = String::from_utf8(reply)?.parse()?;
+
+    for (port, listener) in request.tcp_ports.into_iter().zip(tcp_listeners) {
+        if timeout(PROBE_TIMEOUT, listener.accept()).await.is_err() {
+            client
+                .record_failure(format!("tcp port {} was not reachable", port))
+                .await?;
+        }
+    }
+    for (port, socket) in request.udp_ports.into_iter().zip(udp_sockets) {
+        let mut buf = [0u8; 64];
+        if timeout(PROBE_TIMEOUT, socket.recv_from(&mut buf)).await.is_err() {
+            client
+                .record_failure(format!("udp port {} was not reachable", port))
+                .await?;
+        }
+    }
+
+    Ok(observed_addr)
+}
@@ -0,0 +1,95 @@
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+
+use testground::client::Client;
+
+/// How long to wait for the server to either drop the connection or send
+/// data before assuming it's the final, permanently-open connection.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long the server holds the final accepted connection open, longer
+/// than `READ_TIMEOUT`, so the connector has a chance to confirm it's
+/// genuinely stable.
+const STABLE_HOLD: Duration = Duration::from_secs(10);
+
+pub struct Reconnectable {
+    addr: SocketAddr,
+    stream: TcpStream,
+}
+
+impl Reconnectable {
+    pub async fn connect(addr: SocketAddr) -> Result<Self, Box<dyn std::error::Error>> {
+        let stream = TcpStream::connect(addr).await?;
+        Ok(Self { addr, stream })
+    }
+
+    pub async fn reconnect(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.stream = TcpStream::connect(self.addr).await?;
+        Ok(())
+    }
+}
+
+pub async fn run_drop_server(
+    listener: &TcpListener,
+    drops: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for _ in 0..drops {
+        let (stream, _) = listener.accept().await?;
+        drop(stream);
+    }
+    let (stream, _) = listener.accept().await?;
+    tokio::time::sleep(STABLE_HOLD).await;
+    drop(stream);
+    Ok(())
+}
+
+pub async fn connect_with_resilience(
+    client: &Client,
+    addr: SocketAddr,
+    max_attempts: u32,
+) -> Result<Reconnectable, Box<dyn std::error::Error>> {
+    let mut reconnectable = Reconnectable::connect(addr).await?;
+    let mut reconnects = 0u32;
+
+    for _ in 0..max_attempts {
+        if !dropped(&mut reconnectable).await? {
+            break;
+        }
+
+        let started = Instant::now();
+        reconnectable.reconnect().await?;
+        reconnects += 1;
+        client
+            .record_metric(
+                format!("reconnect_latency_attempt_{reconnects}"),
+                started.elapsed().as_secs_f64(),
+            )
+            .await?;
+    }
+
+    if dropped(&mut reconnectable).await? {
+        return Err("connection dropped again after exhausting reconnect attempts".into());
+    }
+
+    client
+        .record_metric("reconnect_count", reconnects as f64)
+        .await?;
+
+    Ok(reconnectable)
+}
+
+/// Reads with a timeout to check whether the peer closed the connection.
+/// `Ok(false)` means it's still open: either data arrived or the read
+/// simply timed out.
+async fn dropped(reconnectable: &mut Reconnectable) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut probe = [0u8; 1];
+    match timeout(READ_TIMEOUT, reconnectable.stream.read(&mut probe)).await {
+        Ok(Ok(0)) => Ok(true),
+        Ok(Ok(_)) | Err(_) => Ok(false),
+        Ok(Err(e)) => Err(e.into()),
+    }
+}
@@ -0,0 +1,102 @@
+use std::process::Command;
+
+use testground::client::Client;
+
+#[derive(Debug, Clone)]
+pub struct PeerInfo {
+    pub peer_id: String,
+    pub multiaddr: String,
+    pub latency: Option<String>,
+    pub muxer: Option<String>,
+    pub protocols: Vec<String>,
+}
+
+/// Runs on a blocking thread so the subprocess doesn't stall the tokio executor.
+pub async fn connected_peers() -> Result<Vec<PeerInfo>, Box<dyn std::error::Error>> {
+    let output = tokio::task::spawn_blocking(|| {
+        Command::new("ipfs")
+            .args(["swarm", "peers", "--enc=json", "--streams", "--latency"])
+            .output()
+    })
+    .await??;
+
+    if !output.status.success() {
+        return Err(format!(
+            "`ipfs swarm peers` exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        )
+        .into());
+    }
+
+    let response: SwarmPeersResponse = serde_json::from_slice(&output.stdout)?;
+    Ok(response.peers.into_iter().map(PeerInfo::from).collect())
+}
+
+pub async fn assert_peered(
+    client: &Client,
+    expected_peer_id: &str,
+    expected_transport: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let peers = match connected_peers().await {
+        Ok(peers) => peers,
+        Err(e) => {
+            client
+                .record_failure(format!("could not query local swarm state: {e}"))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let peered = peers
+        .iter()
+        .any(|peer| peer.peer_id == expected_peer_id && peer.multiaddr.contains(expected_transport));
+
+    if !peered {
+        client
+            .record_failure(format!(
+                "transport connection to {expected_peer_id} never became a libp2p peering over {expected_transport}"
+            ))
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[derive(serde::Deserialize)]
+struct SwarmPeersResponse {
+    #[serde(rename = "Peers", default)]
+    peers: Vec<RawPeer>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawPeer {
+    #[serde(rename = "Peer")]
+    peer: String,
+    #[serde(rename = "Addr")]
+    addr: String,
+    #[serde(rename = "Latency", default)]
+    latency: Option<String>,
+    #[serde(rename = "Muxer", default)]
+    muxer: Option<String>,
+    #[serde(rename = "Streams", default)]
+    streams: Vec<RawStream>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawStream {
+    #[serde(rename = "Protocol")]
+    protocol: String,
+}
+
+impl From<RawPeer> for PeerInfo {
+    fn from(raw: RawPeer) -> Self {
+        PeerInfo {
+            peer_id: raw.peer,
+            multiaddr: raw.addr,
+            latency: raw.latency,
+            muxer: raw.muxer,
+            protocols: raw.streams.into_iter().map(|stream| stream.protocol).collect(),
+        }
+    }
+}
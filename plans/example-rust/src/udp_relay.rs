@@ -0,0 +1,72 @@
+use std::net::SocketAddr;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UdpSocket;
+
+const MAX_PAYLOAD_LEN: usize = 1024;
+
+pub async fn run_echo_server(local_addr: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
+    let socket = UdpSocket::bind(local_addr).await?;
+
+    let mut buf = [0u8; 2 + MAX_PAYLOAD_LEN];
+    let (len, peer_addr) = socket.recv_from(&mut buf[2..]).await?;
+    socket.connect(peer_addr).await?;
+
+    buf[0..2].copy_from_slice(&(len as u16).to_be_bytes());
+    socket.send(&buf[..2 + len]).await?;
+
+    Ok(())
+}
+
+pub async fn connect_and_verify_echo(
+    local_addr: SocketAddr,
+    peer_addr: SocketAddr,
+    payload: &[u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    assert!(payload.len() <= MAX_PAYLOAD_LEN, "payload too large to frame");
+
+    let socket = UdpSocket::bind(local_addr).await?;
+    socket.connect(peer_addr).await?;
+
+    socket.send(payload).await?;
+
+    let mut buf = [0u8; 2 + MAX_PAYLOAD_LEN];
+    let len = socket.recv(&mut buf).await?;
+    let echoed = read_frame(&buf[..len])?;
+
+    if echoed != payload {
+        return Err("echoed UDP payload did not match what was sent".into());
+    }
+
+    Ok(())
+}
+
+fn read_frame(buf: &[u8]) -> Result<&[u8], Box<dyn std::error::Error>> {
+    if buf.len() < 2 {
+        return Err("buffer too short to contain a length prefix".into());
+    }
+    let len = u16::from_be_bytes([buf[0], buf[1]]) as usize;
+    buf.get(2..2 + len)
+        .ok_or_else(|| "buffer shorter than its declared length".into())
+}
+
+pub async fn read_tcp_frame<R: AsyncReadExt + Unpin>(
+    reader: &mut R,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut len_buf = [0u8; 2];
+    reader.read_exact(&mut len_buf).await?;
+    let len = u16::from_be_bytes(len_buf) as usize;
+
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).await?;
+    Ok(payload)
+}
+
+pub async fn write_tcp_frame<W: AsyncWriteExt + Unpin>(
+    writer: &mut W,
+    payload: &[u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    writer.write_all(&(payload.len() as u16).to_be_bytes()).await?;
+    writer.write_all(payload).await?;
+    Ok(())
+}